@@ -0,0 +1,45 @@
+use argh::FromArgs;
+
+/// Automatically switch between day and night themes based on sunrise/sunset.
+#[derive(FromArgs, Debug)]
+pub struct EnvOpt {
+    #[argh(subcommand)]
+    pub command: Subcommand,
+}
+
+#[derive(FromArgs, Debug)]
+#[argh(subcommand)]
+pub enum Subcommand {
+    Once(OnceCmd),
+    Watch(WatchCmd),
+    Init(InitCmd),
+}
+
+/// Compute the current theme and apply it once, then exit.
+#[derive(FromArgs, Debug)]
+#[argh(subcommand, name = "once")]
+pub struct OnceCmd {
+    /// manual latitude, skips the IP lookup when given together with --lon
+    #[argh(option)]
+    pub lat: Option<f64>,
+    /// manual longitude, skips the IP lookup when given together with --lat
+    #[argh(option)]
+    pub lon: Option<f64>,
+}
+
+/// Keep running, sleeping until each sunrise/sunset and switching themes then.
+#[derive(FromArgs, Debug)]
+#[argh(subcommand, name = "watch")]
+pub struct WatchCmd {
+    /// manual latitude, skips the IP lookup when given together with --lon
+    #[argh(option)]
+    pub lat: Option<f64>,
+    /// manual longitude, skips the IP lookup when given together with --lat
+    #[argh(option)]
+    pub lon: Option<f64>,
+}
+
+/// Write a default config file to the platform config directory.
+#[derive(FromArgs, Debug)]
+#[argh(subcommand, name = "init")]
+pub struct InitCmd {}
@@ -0,0 +1,127 @@
+use anyhow::{Context, Result};
+use directories_next::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// User-editable settings, loaded from `config.toml` in the platform config dir.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    /// Handlebars template for the shell command run when switching to the
+    /// day theme, e.g. `notify-send "Switched to {{theme}} at {{sunrise}}"`.
+    /// Available variables: `theme`, `sunrise`, `sunset`, `lat` and `lon`.
+    pub day_command: String,
+    /// Handlebars template for the shell command run when switching to the
+    /// night theme. See [`Config::day_command`] for the available variables.
+    pub night_command: String,
+    /// Extra environment variables set before running either command.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Manual latitude, used instead of an IP lookup when set alongside `lon`.
+    #[serde(default)]
+    pub lat: Option<f64>,
+    /// Manual longitude, used instead of an IP lookup when set alongside `lat`.
+    #[serde(default)]
+    pub lon: Option<f64>,
+    /// Minutes to shift the computed sunrise by (negative makes day start earlier).
+    #[serde(default)]
+    pub sunrise_offset: i64,
+    /// Minutes to shift the computed sunset by (positive makes night start later).
+    #[serde(default)]
+    pub sunset_offset: i64,
+    /// Which point in the dawn/dusk transition counts as the day/night boundary.
+    #[serde(default)]
+    pub twilight: Twilight,
+}
+
+/// Which named twilight phase counts as the day/night boundary.
+///
+/// This does *not* solve for the sun's actual horizon angle (`spa` only
+/// exposes the geometric sunrise/sunset); each variant instead widens the
+/// day window by a fixed number of minutes via
+/// [`Twilight::fixed_offset_minutes`]. The real civil/nautical/astronomical
+/// twilight duration varies with latitude and season — from well under the
+/// fixed value near the equator to hours at high latitudes — so treat this
+/// as a rough "a bit before/after sunrise/sunset" knob, not an astronomically
+/// accurate twilight calculation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Twilight {
+    /// The sun's geometric position, i.e. `spa`'s raw sunrise/sunset.
+    #[default]
+    Geometric,
+    /// Approximates civil twilight with a fixed offset.
+    Civil,
+    /// Approximates nautical twilight with a fixed offset.
+    Nautical,
+    /// Approximates astronomical twilight with a fixed offset.
+    Astronomical,
+}
+
+impl Twilight {
+    /// Fixed minutes by which this phase precedes sunrise / follows sunset.
+    /// A rough approximation only — see the type-level doc comment.
+    pub fn fixed_offset_minutes(&self) -> i64 {
+        match self {
+            Self::Geometric => 0,
+            Self::Civil => 30,
+            Self::Nautical => 60,
+            Self::Astronomical => 90,
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            day_command: "echo switching to day theme".to_owned(),
+            night_command: "echo switching to night theme".to_owned(),
+            env: HashMap::new(),
+            lat: None,
+            lon: None,
+            sunrise_offset: 0,
+            sunset_offset: 0,
+            twilight: Twilight::default(),
+        }
+    }
+}
+
+fn config_path() -> Result<PathBuf> {
+    let dirs = ProjectDirs::from("", "", "auto-night-mode")
+        .context("could not determine config directory")?;
+    Ok(dirs.config_dir().join("config.toml"))
+}
+
+impl Config {
+    /// Loads the config from disk, writing out the default if none exists yet.
+    /// A present-but-unreadable config (e.g. a permissions error) is
+    /// propagated rather than silently overwritten.
+    pub fn load_or_init() -> Result<Self> {
+        let path = config_path()?;
+
+        match std::fs::read_to_string(&path) {
+            Ok(content) => Ok(toml::from_str(&content)?),
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                let config = Self::default();
+                config.write(&path)?;
+                Ok(config)
+            }
+            Err(e) => Err(e).context("could not read config file"),
+        }
+    }
+
+    /// Writes the default config to disk, overwriting any existing file.
+    pub fn init() -> Result<()> {
+        Self::default().write(&config_path()?)
+    }
+
+    fn write(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
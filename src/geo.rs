@@ -0,0 +1,62 @@
+use anyhow::Result;
+
+/// Where to source the coordinates used to estimate sunrise/sunset.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GeoProvider {
+    /// Look up the approximate location from the public IP address.
+    IpApi,
+    /// Use a fixed latitude/longitude, skipping the network entirely.
+    Manual { lat: f64, lon: f64 },
+}
+
+impl GeoProvider {
+    /// Picks manual coordinates if both are given, otherwise falls back to the IP API.
+    pub fn from_coords(lat: Option<f64>, lon: Option<f64>) -> Self {
+        match (lat, lon) {
+            (Some(lat), Some(lon)) => Self::Manual { lat, lon },
+            _ => Self::IpApi,
+        }
+    }
+
+    pub fn resolve(&self) -> Result<(f64, f64)> {
+        match self {
+            Self::Manual { lat, lon } => Ok((*lat, *lon)),
+            Self::IpApi => {
+                #[derive(serde::Deserialize)]
+                struct IpInfo {
+                    lat: f64,
+                    lon: f64,
+                }
+
+                let IpInfo { lat, lon } = minreq::get("http://ip-api.com/json/")
+                    .send()?
+                    .json()
+                    .map_err(|e| {
+                        log::error!("Bad response from IP API: {}", e);
+                        e
+                    })?;
+
+                Ok((lat, lon))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_coords_is_manual_only_when_both_present() {
+        assert_eq!(
+            GeoProvider::from_coords(Some(51.5), Some(-0.1)),
+            GeoProvider::Manual {
+                lat: 51.5,
+                lon: -0.1
+            }
+        );
+        assert_eq!(GeoProvider::from_coords(Some(51.5), None), GeoProvider::IpApi);
+        assert_eq!(GeoProvider::from_coords(None, Some(-0.1)), GeoProvider::IpApi);
+        assert_eq!(GeoProvider::from_coords(None, None), GeoProvider::IpApi);
+    }
+}
@@ -1,13 +1,89 @@
+mod cli;
+mod config;
+mod geo;
+mod template;
+
 use anyhow::Result;
-use chrono::{DateTime, Local, NaiveTime, Utc};
+use chrono::{DateTime, Duration as ChronoDuration, Local, NaiveTime, Timelike, Utc};
+use cli::{EnvOpt, Subcommand};
+use config::Config;
 use directories_next::BaseDirs;
+use geo::GeoProvider;
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::time::Duration;
+
+/// Upper bound on how long `watch` sleeps between wake-ups, so that a
+/// midnight crossing or clock change still gets re-evaluated promptly.
+const MAX_SLEEP: Duration = Duration::from_secs(4 * 60 * 60);
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct LocationInfo {
     sunset: NaiveTime,
     sunrise: NaiveTime,
+    lat: f64,
+    lon: f64,
+}
+
+/// Shifts a `NaiveTime` by a signed number of minutes, clamped to the same
+/// calendar day. This only prevents the shift from wrapping past midnight —
+/// it does *not* by itself guarantee `sunrise < sunset`, so callers that
+/// shift both ends must still enforce that ordering (see `ordered_window`).
+fn offset_time(time: NaiveTime, offset_minutes: i64) -> NaiveTime {
+    let minutes_since_midnight = time.num_seconds_from_midnight() as i64 / 60;
+    let shifted = (minutes_since_midnight + offset_minutes).clamp(0, 23 * 60 + 59);
+
+    NaiveTime::from_hms(shifted as u32 / 60, shifted as u32 % 60, 0)
+}
+
+/// Minutes-since-midnight for a `NaiveTime`, as an `i64` for arithmetic.
+fn minutes_of(time: NaiveTime) -> i64 {
+    time.num_seconds_from_midnight() as i64 / 60
+}
+
+/// Guarantees `sunrise < sunset` after offsetting, which `get_theme` relies
+/// on. If the offsets pushed sunrise past (or onto) sunset, pull them apart
+/// to the smallest valid window (one minute) starting at the shifted sunrise
+/// instead of silently collapsing or inverting the day.
+fn ordered_window(sunrise: NaiveTime, sunset: NaiveTime) -> (NaiveTime, NaiveTime) {
+    let (sunrise_min, sunset_min) = (minutes_of(sunrise), minutes_of(sunset));
+
+    if sunrise_min < sunset_min {
+        return (sunrise, sunset);
+    }
+
+    let sunset_min = (sunrise_min + 1).min(23 * 60 + 59);
+    let sunrise_min = (sunset_min - 1).max(0);
+
+    (
+        NaiveTime::from_hms((sunrise_min as u32) / 60, (sunrise_min as u32) % 60, 0),
+        NaiveTime::from_hms((sunset_min as u32) / 60, (sunset_min as u32) % 60, 0),
+    )
+}
+
+/// Duration from `now` until the next of today's/tomorrow's `sunrise`/`sunset`,
+/// capped at `MAX_SLEEP`.
+fn next_transition_duration(
+    sunrise: NaiveTime,
+    sunset: NaiveTime,
+    now: DateTime<Local>,
+) -> Duration {
+    let today = now.date();
+    let tomorrow = today + ChronoDuration::days(1);
+
+    [
+        today.and_time(sunrise),
+        today.and_time(sunset),
+        tomorrow.and_time(sunrise),
+        tomorrow.and_time(sunset),
+    ]
+    .iter()
+    .flatten()
+    .filter(|at| **at > now)
+    .min()
+    .and_then(|at| (*at - now).to_std().ok())
+    .unwrap_or(MAX_SLEEP)
+    .min(MAX_SLEEP)
 }
 
 fn get_cache_path() -> PathBuf {
@@ -19,16 +95,14 @@ fn get_cache_path() -> PathBuf {
 impl LocationInfo {
     pub fn get_cached() -> Option<LocationInfo> {
         if let Ok(content) = std::fs::read_to_string(get_cache_path()) {
-            let times = content
-                .split(",")
-                .map(NaiveTime::from_str)
-                .collect::<Result<Vec<NaiveTime>, _>>()
-                .ok()?;
-
-            match times.as_slice() {
-                [sunset, sunrise] => Some(LocationInfo {
-                    sunset: *sunset,
-                    sunrise: *sunrise,
+            let fields: Vec<&str> = content.split(",").collect();
+
+            match fields.as_slice() {
+                [sunset, sunrise, lat, lon] => Some(LocationInfo {
+                    sunset: NaiveTime::from_str(sunset).ok()?,
+                    sunrise: NaiveTime::from_str(sunrise).ok()?,
+                    lat: lat.parse().ok()?,
+                    lon: lon.parse().ok()?,
                 }),
                 _ => None,
             }
@@ -40,7 +114,10 @@ impl LocationInfo {
     pub fn cache(&self) -> Result<()> {
         std::fs::write(
             get_cache_path(),
-            format!("{},{}", self.sunset, self.sunrise),
+            format!(
+                "{},{},{},{}",
+                self.sunset, self.sunrise, self.lat, self.lon
+            ),
         )?;
 
         Ok(())
@@ -55,22 +132,31 @@ impl LocationInfo {
         }
     }
 
-    fn estimate() -> Result<Self> {
-        #[derive(serde::Deserialize)]
-        struct IpInfo {
-            #[serde(rename = "latitude")]
-            lat: f64,
-            #[serde(rename = "longitude")]
-            lon: f64,
-        }
+    /// How long to sleep before the next sunrise/sunset boundary, capped at
+    /// `MAX_SLEEP` so the schedule gets re-evaluated even if nothing ever
+    /// matches (e.g. a clock change).
+    pub fn duration_until_next_transition(&self) -> Duration {
+        next_transition_duration(self.sunrise, self.sunset, Local::now())
+    }
 
-        let IpInfo { lat, lon } = minreq::get("https://freegeoip.app/json/")
-            .send()?
-            .json()
-            .map_err(|e| {
-                log::error!("Bad response from IP API: {}", e);
-                e
-            })?;
+    pub fn sunrise(&self) -> NaiveTime {
+        self.sunrise
+    }
+
+    pub fn sunset(&self) -> NaiveTime {
+        self.sunset
+    }
+
+    pub fn lat(&self) -> f64 {
+        self.lat
+    }
+
+    pub fn lon(&self) -> f64 {
+        self.lon
+    }
+
+    fn estimate(provider: &GeoProvider, config: &Config) -> Result<Self> {
+        let (lat, lon) = provider.resolve()?;
 
         let (sunset, sunrise) = match spa::calc_sunrise_and_set(Utc::now(), lat, lon)? {
             spa::SunriseAndSet::Daylight(set, rise) => {
@@ -83,9 +169,24 @@ impl LocationInfo {
                 NaiveTime::from_hms(0, 0, 0),
             ),
         };
-        Self { sunset, sunrise }.cache()?;
 
-        Ok(Self { sunset, sunrise })
+        let dawn_shift = config.sunrise_offset - config.twilight.fixed_offset_minutes();
+        let dusk_shift = config.sunset_offset + config.twilight.fixed_offset_minutes();
+
+        let (sunrise, sunset) = ordered_window(
+            offset_time(sunrise, dawn_shift),
+            offset_time(sunset, dusk_shift),
+        );
+
+        let info = Self {
+            sunset,
+            sunrise,
+            lat,
+            lon,
+        };
+        info.cache()?;
+
+        Ok(info)
     }
 }
 
@@ -96,52 +197,173 @@ pub enum Theme {
 }
 
 impl Theme {
-    fn set(&self) -> Result<()> {
-        std::process::Command::new("systemctl")
-            .args(&[
-                "--user",
-                "set-environment",
-                &format!(
-                    "THEME={}",
-                    match self {
-                        Self::Night => "dark",
-                        _ => "light",
-                    }
-                ),
-            ])
-            .spawn()?;
-
-        std::process::Command::new("/home/greg/.dotfiles/bin/theme").spawn()?;
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Self::Night => "night",
+            Self::Day => "day",
+        }
+    }
+
+    fn command<'a>(&self, config: &'a Config) -> &'a str {
+        match self {
+            Self::Night => &config.night_command,
+            Self::Day => &config.day_command,
+        }
+    }
+
+    fn set(&self, config: &Config, location: &LocationInfo) -> Result<()> {
+        let command = template::render(self.command(config), self, location)?;
+
+        let mut cmd = std::process::Command::new("sh");
+        cmd.args(["-c", &command]).envs(&config.env);
+
+        let status = cmd.status()?;
+        if !status.success() {
+            log::error!("theme command exited with {}", status);
+        }
 
         Ok(())
     }
 }
 
-fn main() -> Result<()> {
-    flexi_logger::Logger::with_env().start()?;
+fn once(config: &Config, provider: GeoProvider) -> Result<()> {
+    let location = LocationInfo::estimate(&provider, config)?;
+    log::info!("{:?}", location);
 
+    location.get_theme().set(config, &location)
+}
+
+fn watch(config: &Config, provider: GeoProvider) -> Result<()> {
     let cached_location = LocationInfo::get_cached();
     if let Some(cached_location) = cached_location {
-        cached_location.get_theme().set()?;
+        cached_location.get_theme().set(config, &cached_location)?;
     }
 
-    let location = LocationInfo::estimate()?;
+    let mut location = LocationInfo::estimate(&provider, config)?;
     log::info!("{:?}", location);
 
     // Immediately set the appropriate theme
     if !matches!(cached_location, Some(l) if l == location) {
-        location.get_theme().set()?;
+        location.get_theme().set(config, &location)?;
     }
 
     let mut prev_theme = location.get_theme();
 
     loop {
-        std::thread::sleep(std::time::Duration::from_secs(30));
+        let sleep_duration = location.duration_until_next_transition();
+        log::info!("sleeping for {:?}", sleep_duration);
+        std::thread::sleep(sleep_duration);
+
+        // Sunrise/sunset drift day to day, so recompute on every wake-up.
+        location = LocationInfo::estimate(&provider, config)?;
 
         let theme = location.get_theme();
         if theme != prev_theme {
-            theme.set()?;
+            theme.set(config, &location)?;
             prev_theme = theme;
         }
     }
 }
+
+fn run(cmd: Subcommand) -> Result<()> {
+    match cmd {
+        Subcommand::Init(_) => Config::init(),
+        Subcommand::Once(opt) => {
+            let config = Config::load_or_init()?;
+            let provider = GeoProvider::from_coords(
+                opt.lat.or(config.lat),
+                opt.lon.or(config.lon),
+            );
+            once(&config, provider)
+        }
+        Subcommand::Watch(opt) => {
+            let config = Config::load_or_init()?;
+            let provider = GeoProvider::from_coords(
+                opt.lat.or(config.lat),
+                opt.lon.or(config.lon),
+            );
+            watch(&config, provider)
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    flexi_logger::Logger::with_env().start()?;
+
+    let opt: EnvOpt = argh::from_env();
+    run(opt.command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn local(hour: u32, min: u32) -> DateTime<Local> {
+        Local.ymd(2026, 7, 28).and_hms(hour, min, 0)
+    }
+
+    #[test]
+    fn ordered_window_keeps_already_ordered_times() {
+        let sunrise = NaiveTime::from_hms(6, 0, 0);
+        let sunset = NaiveTime::from_hms(20, 0, 0);
+
+        assert_eq!(ordered_window(sunrise, sunset), (sunrise, sunset));
+    }
+
+    #[test]
+    fn ordered_window_pulls_apart_an_inverted_pair() {
+        let sunrise = NaiveTime::from_hms(21, 0, 0);
+        let sunset = NaiveTime::from_hms(20, 0, 0);
+
+        let (sunrise, sunset) = ordered_window(sunrise, sunset);
+
+        assert!(sunrise < sunset);
+        assert_eq!(sunset, NaiveTime::from_hms(21, 1, 0));
+    }
+
+    #[test]
+    fn next_transition_picks_todays_sunset_before_it_passes() {
+        let sunrise = NaiveTime::from_hms(6, 0, 0);
+        let sunset = NaiveTime::from_hms(20, 0, 0);
+
+        let duration = next_transition_duration(sunrise, sunset, local(18, 0));
+
+        assert_eq!(duration, Duration::from_secs(2 * 60 * 60));
+    }
+
+    #[test]
+    fn next_transition_rolls_over_to_tomorrows_sunrise() {
+        let sunrise = NaiveTime::from_hms(6, 0, 0);
+        let sunset = NaiveTime::from_hms(20, 0, 0);
+
+        // After tonight's sunset, the next boundary (tomorrow's 06:00 sunrise)
+        // is over 4h away, so this also exercises the MAX_SLEEP cap.
+        let duration = next_transition_duration(sunrise, sunset, local(21, 0));
+
+        assert_eq!(duration, MAX_SLEEP);
+    }
+
+    #[test]
+    fn next_transition_rolls_over_within_the_cap() {
+        let sunrise = NaiveTime::from_hms(2, 0, 0);
+        let sunset = NaiveTime::from_hms(20, 0, 0);
+
+        // Both of today's boundaries have already passed by 23:00, so the
+        // next one is tomorrow's 02:00 sunrise, 3h away.
+        let duration = next_transition_duration(sunrise, sunset, local(23, 0));
+
+        assert_eq!(duration, Duration::from_secs(3 * 60 * 60));
+    }
+
+    #[test]
+    fn next_transition_is_capped_at_max_sleep() {
+        let sunrise = NaiveTime::from_hms(6, 0, 0);
+        let sunset = NaiveTime::from_hms(20, 0, 0);
+
+        // Just after sunrise, the next boundary (sunset) is far beyond MAX_SLEEP.
+        let duration = next_transition_duration(sunrise, sunset, local(6, 1));
+
+        assert_eq!(duration, MAX_SLEEP);
+    }
+}
@@ -0,0 +1,35 @@
+use crate::LocationInfo;
+use crate::Theme;
+use anyhow::Result;
+use handlebars::Handlebars;
+use serde::Serialize;
+
+/// Variables available to `day_command`/`night_command` templates.
+#[derive(Serialize)]
+struct CommandContext {
+    theme: &'static str,
+    sunrise: String,
+    sunset: String,
+    lat: f64,
+    lon: f64,
+}
+
+/// Renders a `day_command`/`night_command` template against the current
+/// theme and location, e.g. `notify-send "Switched to {{theme}} at {{sunset}}"`.
+pub fn render(template: &str, theme: &Theme, location: &LocationInfo) -> Result<String> {
+    let context = CommandContext {
+        theme: theme.name(),
+        sunrise: location.sunrise().to_string(),
+        sunset: location.sunset().to_string(),
+        lat: location.lat(),
+        lon: location.lon(),
+    };
+
+    let mut handlebars = Handlebars::new();
+    // The rendered string is passed straight to `sh -c`, not HTML, so don't
+    // let handlebars mangle `&`/`<`/`"` into HTML entities.
+    handlebars.register_escape_fn(handlebars::no_escape);
+    handlebars.set_strict_mode(true);
+
+    Ok(handlebars.render_template(template, &context)?)
+}